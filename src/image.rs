@@ -1,13 +1,24 @@
-//! Process an image into a skybox
+//! Resolve a skybox source (a net/cross image, separate face images, a
+//! cubemap/KTX2 asset, or an equirectangular panorama) into the stacked
+//! `Image` that [`crate::sync_skybox`] reinterprets as a cube array.
 //!
-//! This makes significant assumptions about the image.
+//! This module makes significant assumptions about the image in all cases:
 //! * The skybox is a cube.
 //! * The y-axis is up.
-//! * The image provides a net for a cube in the same format as
-//!   `assets/sky1.png`, ie. with the vertical faces in a strip
-//!   in the middle and the top and bottom above and below the
-//!   third square from the left in the strip.
 //! * The image doesn't have a specific "front" direction.
+//!
+//! # Net/cross images ([`get_skybox`])
+//!
+//! By default, [`get_skybox`] expects a net for a cube in the same format
+//! as `assets/sky1.png`, ie. with the vertical faces in a strip in the
+//! middle and the top and bottom above and below the third square from the
+//! left in the strip ([`NetLayout::cross_strip`]). Other conventions, such
+//! as a horizontal-cross layout with the top/bottom faces rotated, can be
+//! parsed by passing a different [`NetLayout`] (a built-in preset such as
+//! [`NetLayout::horizontal_cross`], or one built from custom
+//! [`FaceLayout`]s via [`NetLayout::new`]).
+//!
+//! Beyond the net convention itself, [`get_skybox`] further assumes:
 //! * It has an exact background colour outside the net, and that
 //!   exact colour does not appear around the edge the net.
 //! * The net is well-aligned with the image border.
@@ -26,8 +37,19 @@
 //! Windows) may not actually flip the underlying data read by this
 //! module. Instead, you may need to copy the flipped image (in "Paint")
 //! and then paste it into a new file.
+//!
+//! # Other sources
+//!
+//! Skyboxes authored as six separate face images (either named by
+//! convention or given explicitly) skip net detection entirely and go
+//! through [`get_skybox_from_faces`]/[`get_skybox_from_face_paths`], which
+//! just validate and stack them. A single equirectangular ("360 photo")
+//! panorama is baked into six faces by [`get_skybox_from_equirectangular`].
+//! A pre-built KTX2 cubemap skips this module altogether and is loaded
+//! directly through Bevy's `AssetServer`.
 
 use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
 use image::{
     DynamicImage, GenericImage, GenericImageView, ImageBuffer, ImageReader, Rgba, RgbaImage,
 };
@@ -45,11 +67,26 @@ pub enum ImageError {
     NetNotFound,
     NotAligned,
     CopyError,
+    FaceSizeMismatch,
 }
 
+/// The conventional suffixes used to name the six faces of a cubemap, in
+/// the `+X,-X,+Y,-Y,+Z,-Z` order expected by [`get_skybox_from_faces`].
+///
+/// Each face is tried against every alias in its row, so a skybox named
+/// with either `_px`/`_nx`/... or `_right`/`_left`/... is accepted.
+const FACE_SUFFIXES: [&[&str]; 6] = [
+    &["px", "right"],
+    &["nx", "left"],
+    &["py", "up"],
+    &["ny", "down"],
+    &["pz", "front"],
+    &["nz", "back"],
+];
+
 /// Get the skybox mesh, including the uv values for the given texture
 /// image. The box has unit edges is centred on the origin.
-pub fn get_skybox(image_name: &str) -> Result<Image, ImageError> {
+pub fn get_skybox(image_name: &str, layout: &NetLayout) -> Result<Image, ImageError> {
     // Load the image for processing.
     let root_path = std::env::var_os("CARGO_MANIFEST_DIR").ok_or(ImageError::BadEnv)?;
     let path = Path::new(&root_path).join("assets").join(image_name);
@@ -64,10 +101,538 @@ pub fn get_skybox(image_name: &str) -> Result<Image, ImageError> {
     })?;
     let orig_rgba = DynamicImage::ImageRgba8(orig_image.to_rgba8());
     let meas = ImageMeasurements::find_measurements(&orig_rgba)?;
-    let shaped_image = meas.new_image(&orig_rgba)?;
+    let shaped_image = meas.new_image(&orig_rgba, layout)?;
     Ok(shaped_image)
 }
 
+/// Describes where one face of the cube lives within a net image, as
+/// grid indices into `ImageMeasurements::vec_x`/`vec_y`, plus any
+/// orientation correction needed so the face lands right-way-up in the
+/// output (some net conventions author a face rotated or mirrored
+/// relative to this crate's default).
+#[derive(Clone, Copy)]
+pub struct FaceLayout {
+    /// Column index into `ImageMeasurements::vec_x`.
+    x_idx: usize,
+    /// Row index into `ImageMeasurements::vec_y`.
+    y_idx: usize,
+    /// Mirror the face horizontally (source `u` becomes `side - 1 - u`).
+    flip_x: bool,
+    /// Mirror the face vertically (source `v` becomes `side - 1 - v`).
+    flip_y: bool,
+    /// Swap `u`/`v` (diagonal flip), applied before `flip_x`/`flip_y`.
+    transpose: bool,
+}
+
+impl FaceLayout {
+    /// Place a face at grid cell `(x_idx, y_idx)` of the net's 4-column,
+    /// 3-row strip-with-cap grid (the same grid [`find_measurements`]
+    /// locates for every net, regardless of which faces occupy which
+    /// cells): `x_idx` is `0..=3`, `y_idx` is `0..=2`. `cross_strip`'s
+    /// column/row assignments (e.g. `FaceLayout::new(3, 1)` for +X) are a
+    /// worked example of addressing this grid.
+    ///
+    /// [`find_measurements`]: ImageMeasurements::find_measurements
+    pub const fn new(x_idx: usize, y_idx: usize) -> Self {
+        FaceLayout {
+            x_idx,
+            y_idx,
+            flip_x: false,
+            flip_y: false,
+            transpose: false,
+        }
+    }
+
+    /// Mirror this face horizontally (source `u` becomes `side - 1 - u`).
+    pub const fn flip_x(mut self) -> Self {
+        self.flip_x = true;
+        self
+    }
+
+    /// Mirror this face vertically (source `v` becomes `side - 1 - v`).
+    pub const fn flip_y(mut self) -> Self {
+        self.flip_y = true;
+        self
+    }
+
+    /// Swap `u`/`v` (diagonal flip), applied before `flip_x`/`flip_y`.
+    pub const fn transpose(mut self) -> Self {
+        self.transpose = true;
+        self
+    }
+}
+
+/// Describes the arrangement (and per-face orientation) of the six cube
+/// faces within a net image, so that skyboxes authored with different
+/// conventions can be read without re-editing the source art.
+///
+/// Select one with [`SkyboxPlugin::with_net_layout`](crate::SkyboxPlugin::with_net_layout).
+#[derive(Clone, Copy)]
+pub struct NetLayout {
+    /// Per-face layout, in +X,-X,+Y,-Y,+Z,-Z order.
+    faces: [FaceLayout; 6],
+}
+
+impl NetLayout {
+    /// Build a fully custom layout from six explicit [`FaceLayout`]s, in
+    /// +X,-X,+Y,-Y,+Z,-Z order, for net conventions beyond the
+    /// [`cross_strip`](Self::cross_strip)/[`horizontal_cross`](Self::horizontal_cross)
+    /// presets.
+    pub const fn new(faces: [FaceLayout; 6]) -> Self {
+        NetLayout { faces }
+    }
+
+    /// The layout this crate has always assumed: a horizontal strip of
+    /// the four side faces (back, left, front, right) with the top and bottom
+    /// faces above and below the third (front) square in the strip, as
+    /// in `assets/sky1.png`.
+    pub fn cross_strip() -> Self {
+        NetLayout {
+            faces: [
+                FaceLayout::new(3, 1), // +X
+                FaceLayout::new(1, 1), // -X
+                FaceLayout::new(2, 0), // +Y
+                FaceLayout::new(2, 2), // -Y
+                FaceLayout::new(2, 1), // +Z
+                FaceLayout::new(0, 1), // -Z
+            ],
+        }
+    }
+
+    /// An alternate net layout seen in some downloaded skybox art, where
+    /// the top and bottom faces are authored rotated 180 degrees
+    /// relative to [`cross_strip`](Self::cross_strip).
+    pub fn horizontal_cross() -> Self {
+        NetLayout {
+            faces: [
+                FaceLayout::new(3, 1), // +X
+                FaceLayout::new(1, 1), // -X
+                FaceLayout::new(2, 0).flip_x().flip_y(), // +Y
+                FaceLayout::new(2, 2).flip_x().flip_y(), // -Y
+                FaceLayout::new(2, 1), // +Z
+                FaceLayout::new(0, 1), // -Z
+            ],
+        }
+    }
+}
+
+impl Default for NetLayout {
+    fn default() -> Self {
+        NetLayout::cross_strip()
+    }
+}
+
+/// Get the skybox image stacked from six separate face images named
+/// `<base_name>_<suffix>.<ext>`, where `<suffix>` is one of the
+/// conventional face names in [`FACE_SUFFIXES`] (e.g. `sky_px.png`,
+/// `sky_right.png`).
+///
+/// Each face image must be square, and all six must be the same size;
+/// they are stacked vertically in +X,-X,+Y,-Y,+Z,-Z order into the same
+/// layout that [`get_skybox`] produces, ready for
+/// `reinterpret_stacked_2d_as_array(6)`.
+pub fn get_skybox_from_faces(base_name: &str) -> Result<Image, ImageError> {
+    let root_path = std::env::var_os("CARGO_MANIFEST_DIR").ok_or(ImageError::BadEnv)?;
+    let assets_dir = Path::new(&root_path).join("assets");
+    let (stem, ext) = split_base_name(base_name);
+
+    let mut faces: Vec<DynamicImage> = Vec::with_capacity(6);
+    for aliases in FACE_SUFFIXES.iter() {
+        let image = aliases
+            .iter()
+            .find_map(|suffix| {
+                let path = assets_dir.join(format!("{stem}_{suffix}.{ext}"));
+                ImageReader::open(&path).ok()?.decode().ok()
+            })
+            .ok_or(ImageError::FileNotFound)?;
+        faces.push(image);
+    }
+
+    stack_faces(faces)
+}
+
+/// Get the skybox image stacked from six explicitly-named face images,
+/// rather than discovering them via [`FACE_SUFFIXES`]. `paths` are in
+/// +X,-X,+Y,-Y,+Z,-Z order and resolved relative to the `assets` folder,
+/// same as [`get_skybox`] and [`get_skybox_from_faces`].
+pub fn get_skybox_from_face_paths(paths: &[String; 6]) -> Result<Image, ImageError> {
+    let root_path = std::env::var_os("CARGO_MANIFEST_DIR").ok_or(ImageError::BadEnv)?;
+    let assets_dir = Path::new(&root_path).join("assets");
+
+    let mut faces: Vec<DynamicImage> = Vec::with_capacity(6);
+    for path in paths {
+        let image = ImageReader::open(assets_dir.join(path))
+            .map_err(|_| ImageError::FileNotFound)?
+            .decode()
+            .map_err(|_| ImageError::DecodeFailed)?;
+        faces.push(image);
+    }
+
+    stack_faces(faces)
+}
+
+/// Validate that six face images are all square and equal-sized, then
+/// stack them vertically in the order given, ready for
+/// `reinterpret_stacked_2d_as_array(6)`.
+fn stack_faces(faces: Vec<DynamicImage>) -> Result<Image, ImageError> {
+    let side = faces[0].width();
+    for face in &faces {
+        if face.width() != side || face.height() != side {
+            return Err(ImageError::FaceSizeMismatch);
+        }
+    }
+
+    let mut new_image = RgbaImage::new(side, side * 6);
+    for (out_idx, face) in faces.iter().enumerate() {
+        new_image
+            .copy_from(&face.to_rgba8(), 0, side * (out_idx as u32))
+            .map_err(|_| ImageError::CopyError)?;
+    }
+
+    Ok(Image::from_dynamic(
+        image::DynamicImage::from(new_image),
+        true,
+        bevy::asset::RenderAssetUsages::all(),
+    ))
+}
+
+/// Bake an equirectangular ("360 photo") panorama into a skybox by
+/// sampling it in each cube face texel's world direction with the standard
+/// lat/long mapping (`u = atan2(d.z, d.x) / (2*PI) + 0.5`,
+/// `v = acos(d.y) / PI`), then stacking the baked faces like [`get_skybox`].
+///
+/// Each face is baked at a quarter of the panorama's width, matching the
+/// usual cube/equirect resolution ratio. Sampling wraps horizontally (so
+/// the longitude seam at `u = 0`/`1` blends cleanly) and clamps vertically
+/// (there's no data beyond the poles to wrap into).
+pub fn get_skybox_from_equirectangular(path: &str) -> Result<Image, ImageError> {
+    let root_path = std::env::var_os("CARGO_MANIFEST_DIR").ok_or(ImageError::BadEnv)?;
+    let assets_dir = Path::new(&root_path).join("assets");
+    let panorama = ImageReader::open(assets_dir.join(path))
+        .map_err(|_| ImageError::FileNotFound)?
+        .decode()
+        .map_err(|_| ImageError::DecodeFailed)?
+        .to_rgba8();
+
+    let side = (panorama.width() / 4).max(1);
+    let mut new_image = RgbaImage::new(side, side * 6);
+    for face in 0..6u32 {
+        for oy in 0..side {
+            for ox in 0..side {
+                let u = (ox as f32 + 0.5) / side as f32;
+                let v = (oy as f32 + 0.5) / side as f32;
+                let dir = cube_direction(face, u, v);
+                let pano_u = dir.z.atan2(dir.x) / std::f32::consts::TAU + 0.5;
+                let pano_v = dir.y.clamp(-1.0, 1.0).acos() / std::f32::consts::PI;
+                let pixel = sample_equirectangular(&panorama, pano_u, pano_v);
+                new_image.put_pixel(ox, side * face + oy, pixel);
+            }
+        }
+    }
+
+    Ok(Image::from_dynamic(
+        image::DynamicImage::from(new_image),
+        true,
+        bevy::asset::RenderAssetUsages::all(),
+    ))
+}
+
+/// Bilinearly sample `image` at normalized `(u, v)`, wrapping horizontally
+/// and clamping vertically, per [`get_skybox_from_equirectangular`].
+fn sample_equirectangular(image: &RgbaImage, u: f32, v: f32) -> Rgba<u8> {
+    let (w, h) = image.dimensions();
+    let x = u.rem_euclid(1.0) * w as f32 - 0.5;
+    let y = (v.clamp(0.0, 1.0) * h as f32 - 0.5).clamp(0.0, h as f32 - 1.0);
+    let (x0f, y0f) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0f, y - y0f);
+
+    let wrap_x = |xi: f32| xi.rem_euclid(w as f32) as u32;
+    let y0 = y0f.max(0.0) as u32;
+    let y1 = (y0 + 1).min(h - 1);
+    let x0 = wrap_x(x0f);
+    let x1 = wrap_x(x0f + 1.0);
+
+    let (p00, p10, p01, p11) = (
+        image.get_pixel(x0, y0).0,
+        image.get_pixel(x1, y0).0,
+        image.get_pixel(x0, y1).0,
+        image.get_pixel(x1, y1).0,
+    );
+
+    let mut out = [0u8; 4];
+    for c in 0..4 {
+        let top = p00[c] as f32 * (1.0 - fx) + p10[c] as f32 * fx;
+        let bottom = p01[c] as f32 * (1.0 - fx) + p11[c] as f32 * fx;
+        out[c] = (top * (1.0 - fy) + bottom * fy).round() as u8;
+    }
+    Rgba(out)
+}
+
+/// Size (in pixels) of each face of the diffuse irradiance cubemap built
+/// by [`diffuse_irradiance`]. Irradiance varies slowly across the sky, so
+/// this can be tiny.
+const IRRADIANCE_FACE_SIZE: u32 = 8;
+
+/// The world-space direction a texel at `(u, v)` (both in `0..1`) of cube
+/// `face` points towards, in our +X,-X,+Y,-Y,+Z,-Z face order.
+fn cube_direction(face: u32, u: f32, v: f32) -> Vec3 {
+    // Map to [-1, 1], with the +v axis pointing down the image (matching
+    // how the faces were copied into the stacked image).
+    let a = 2.0 * u - 1.0;
+    let b = 2.0 * v - 1.0;
+    match face {
+        0 => Vec3::new(1.0, -b, -a),  // +X
+        1 => Vec3::new(-1.0, -b, a),  // -X
+        2 => Vec3::new(a, 1.0, b),    // +Y
+        3 => Vec3::new(a, -1.0, -b),  // -Y
+        4 => Vec3::new(a, -b, 1.0),   // +Z
+        _ => Vec3::new(-a, -b, -1.0), // -Z
+    }
+    .normalize()
+}
+
+/// Sample the nearest texel of a stacked skybox image (the layout produced
+/// by [`get_skybox`]/[`get_skybox_from_faces`], *before*
+/// `reinterpret_stacked_2d_as_array`) in the given world direction.
+fn sample_cubemap(data: &[u8], side: u32, dir: Vec3) -> [u8; 4] {
+    let Vec3 { x, y, z } = dir;
+    let (ax, ay, az) = (x.abs(), y.abs(), z.abs());
+    let (face, u, v) = if ax >= ay && ax >= az {
+        if x > 0.0 { (0, -z / ax, -y / ax) } else { (1, z / ax, -y / ax) }
+    } else if ay >= ax && ay >= az {
+        if y > 0.0 { (2, x / ay, z / ay) } else { (3, x / ay, -z / ay) }
+    } else if z > 0.0 {
+        (4, x / az, -y / az)
+    } else {
+        (5, -x / az, -y / az)
+    };
+
+    let tx = (((u + 1.0) * 0.5) * side as f32).clamp(0.0, side as f32 - 1.0) as u32;
+    let ty = (((v + 1.0) * 0.5) * side as f32).clamp(0.0, side as f32 - 1.0) as u32;
+    let idx = (((face * side + ty) * side + tx) * 4) as usize;
+    [data[idx], data[idx + 1], data[idx + 2], data[idx + 3]]
+}
+
+/// Build a small diffuse irradiance cubemap from an already-stacked
+/// skybox image (the vertically-stacked RGBA8 layout produced by
+/// [`get_skybox`]/[`get_skybox_from_faces`], *before*
+/// `reinterpret_stacked_2d_as_array` is called on it), for use as the
+/// diffuse map of a `bevy::pbr::EnvironmentMapLight`.
+///
+/// For each output texel, its world normal `N` is used to integrate
+/// incoming radiance over the hemisphere on a `phi`/`theta` grid in `N`'s
+/// tangent space, weighted by `cos(theta) * sin(theta)` and normalized by
+/// the sum of those weights, so a uniformly coloured sky yields that same
+/// colour.
+pub fn diffuse_irradiance(stacked: &Image, side: u32) -> Image {
+    const PHI_SAMPLES: u32 = 16;
+    const THETA_SAMPLES: u32 = 8;
+
+    let data = stacked
+        .data
+        .as_ref()
+        .expect("skybox image should have pixel data before GPU upload");
+
+    let mut out = RgbaImage::new(IRRADIANCE_FACE_SIZE, IRRADIANCE_FACE_SIZE * 6);
+    for face in 0..6u32 {
+        for oy in 0..IRRADIANCE_FACE_SIZE {
+            for ox in 0..IRRADIANCE_FACE_SIZE {
+                let u = (ox as f32 + 0.5) / IRRADIANCE_FACE_SIZE as f32;
+                let v = (oy as f32 + 0.5) / IRRADIANCE_FACE_SIZE as f32;
+                let normal = cube_direction(face, u, v);
+                let up = if normal.y.abs() < 0.999 { Vec3::Y } else { Vec3::X };
+                let tangent = up.cross(normal).normalize();
+                let bitangent = normal.cross(tangent);
+
+                let mut sum = [0.0f32; 3];
+                let mut weight_sum = 0.0f32;
+                for t in 0..THETA_SAMPLES {
+                    let theta = (t as f32 + 0.5) / THETA_SAMPLES as f32 * std::f32::consts::FRAC_PI_2;
+                    let (sin_t, cos_t) = theta.sin_cos();
+                    for p in 0..PHI_SAMPLES {
+                        let phi = (p as f32 + 0.5) / PHI_SAMPLES as f32 * std::f32::consts::TAU;
+                        let sample_dir = tangent * (sin_t * phi.cos())
+                            + bitangent * (sin_t * phi.sin())
+                            + normal * cos_t;
+                        let texel = sample_cubemap(data, side, sample_dir);
+                        let weight = cos_t * sin_t;
+                        for c in 0..3 {
+                            sum[c] += texel[c] as f32 * weight;
+                        }
+                        weight_sum += weight;
+                    }
+                }
+
+                let pixel = Rgba([
+                    (sum[0] / weight_sum) as u8,
+                    (sum[1] / weight_sum) as u8,
+                    (sum[2] / weight_sum) as u8,
+                    255,
+                ]);
+                out.put_pixel(ox, face * IRRADIANCE_FACE_SIZE + oy, pixel);
+            }
+        }
+    }
+
+    Image::from_dynamic(
+        image::DynamicImage::from(out),
+        true,
+        bevy::asset::RenderAssetUsages::all(),
+    )
+}
+
+/// Number of mip levels in the specular prefilter cubemap built by
+/// [`specular_prefilter`] (capped by how many times `side` can halve),
+/// spanning roughness 0 (mip 0, mirror) to roughness 1 (the last mip).
+const SPECULAR_MIP_COUNT: u32 = 6;
+
+/// Number of GGX-importance-sampled directions per texel above mip 0.
+/// Specular highlights are small and low-frequency by the time roughness
+/// is non-zero, so this can stay modest.
+const SPECULAR_SAMPLES: u32 = 32;
+
+/// Build a roughness-mipped, GGX-prefiltered specular cubemap from an
+/// already-stacked skybox image (the vertically-stacked RGBA8 layout
+/// produced by [`get_skybox`]/[`get_skybox_from_faces`], *before*
+/// `reinterpret_stacked_2d_as_array` is called on it), for use as the
+/// specular map of a `bevy::pbr::EnvironmentMapLight`.
+///
+/// Mip 0 is an exact (roughness-0, mirror) copy of the source at its
+/// native resolution; each subsequent mip halves resolution and
+/// increases roughness linearly up to 1 at the last mip. Each texel above
+/// mip 0 importance-samples outgoing radiance with a Hammersley sequence
+/// mapped through the GGX normal distribution for that mip's roughness
+/// (the standard split-sum approximation, assuming `V == R == N`),
+/// accumulating `radiance * NdotL` and normalizing by the summed `NdotL`.
+///
+/// The returned `Image`'s raw data is laid out layer-major (all mips of
+/// face 0, then all mips of face 1, ...), matching `wgpu`'s default
+/// `TextureDataOrder` for uploading a multi-mip, multi-layer texture.
+pub fn specular_prefilter(stacked: &Image, side: u32) -> Image {
+    let data = stacked
+        .data
+        .as_ref()
+        .expect("skybox image should have pixel data before GPU upload");
+
+    let mip_count = SPECULAR_MIP_COUNT.min(side.max(1).ilog2() + 1).max(1);
+
+    let mut out = Vec::new();
+    for face in 0..6u32 {
+        for mip in 0..mip_count {
+            let mip_side = (side >> mip).max(1);
+            let roughness = if mip_count <= 1 {
+                0.0
+            } else {
+                mip as f32 / (mip_count - 1) as f32
+            };
+            for oy in 0..mip_side {
+                for ox in 0..mip_side {
+                    let u = (ox as f32 + 0.5) / mip_side as f32;
+                    let v = (oy as f32 + 0.5) / mip_side as f32;
+                    let normal = cube_direction(face, u, v);
+                    let texel = if roughness <= 0.0 {
+                        sample_cubemap(data, side, normal)
+                    } else {
+                        prefilter_texel(data, side, normal, roughness)
+                    };
+                    out.extend_from_slice(&texel);
+                }
+            }
+        }
+    }
+
+    // `Image::new` requires its `data` argument to be sized for exactly
+    // one mip level, so it can't be handed `out`'s full multi-mip chain
+    // directly. Build it with a throwaway single-mip-sized buffer to
+    // satisfy that, then install the real `mip_level_count` and `out`
+    // buffer on the fields directly, bypassing the single-mip assumption.
+    let mip0_len = (side * side * 6 * 4) as usize;
+    let mut image = Image::new(
+        Extent3d {
+            width: side,
+            height: side,
+            depth_or_array_layers: 6,
+        },
+        TextureDimension::D2,
+        vec![0u8; mip0_len],
+        TextureFormat::Rgba8UnormSrgb,
+        bevy::asset::RenderAssetUsages::all(),
+    );
+    image.texture_descriptor.mip_level_count = mip_count;
+    image.data = Some(out);
+    image
+}
+
+/// GGX-importance-sample outgoing radiance towards `normal` at
+/// `roughness`, per [`specular_prefilter`].
+fn prefilter_texel(data: &[u8], side: u32, normal: Vec3, roughness: f32) -> [u8; 4] {
+    let up = if normal.y.abs() < 0.999 { Vec3::Y } else { Vec3::X };
+    let tangent = up.cross(normal).normalize();
+    let bitangent = normal.cross(tangent);
+
+    let mut sum = [0.0f32; 3];
+    let mut weight_sum = 0.0f32;
+    for i in 0..SPECULAR_SAMPLES {
+        let (xi0, xi1) = hammersley(i, SPECULAR_SAMPLES);
+        let h = importance_sample_ggx(xi0, xi1, roughness);
+        let h_world = (tangent * h.x + bitangent * h.y + normal * h.z).normalize();
+        let l = (h_world * (2.0 * normal.dot(h_world)) - normal).normalize();
+
+        let n_dot_l = normal.dot(l);
+        if n_dot_l > 0.0 {
+            let texel = sample_cubemap(data, side, l);
+            for c in 0..3 {
+                sum[c] += texel[c] as f32 * n_dot_l;
+            }
+            weight_sum += n_dot_l;
+        }
+    }
+
+    if weight_sum <= 0.0 {
+        return sample_cubemap(data, side, normal);
+    }
+    [
+        (sum[0] / weight_sum).clamp(0.0, 255.0) as u8,
+        (sum[1] / weight_sum).clamp(0.0, 255.0) as u8,
+        (sum[2] / weight_sum).clamp(0.0, 255.0) as u8,
+        255,
+    ]
+}
+
+/// The `i`-th low-discrepancy Hammersley point of `count`: `(i / count,
+/// van_der_corput(i))`.
+fn hammersley(i: u32, count: u32) -> (f32, f32) {
+    let mut bits = i;
+    bits = (bits << 16) | (bits >> 16);
+    bits = ((bits & 0x5555_5555) << 1) | ((bits & 0xAAAA_AAAA) >> 1);
+    bits = ((bits & 0x3333_3333) << 2) | ((bits & 0xCCCC_CCCC) >> 2);
+    bits = ((bits & 0x0F0F_0F0F) << 4) | ((bits & 0xF0F0_F0F0) >> 4);
+    bits = ((bits & 0x00FF_00FF) << 8) | ((bits & 0xFF00_FF00) >> 8);
+    let van_der_corput = bits as f32 * 2.328_306_4e-10; // 1 / 2^32
+    (i as f32 / count as f32, van_der_corput)
+}
+
+/// Map a low-discrepancy 2D sample `(xi0, xi1)` to a half-vector in
+/// tangent space (`z` along the normal) via the GGX normal distribution
+/// for `roughness`.
+fn importance_sample_ggx(xi0: f32, xi1: f32, roughness: f32) -> Vec3 {
+    let a = roughness * roughness;
+    let phi = std::f32::consts::TAU * xi0;
+    let cos_theta = ((1.0 - xi1) / (1.0 + (a * a - 1.0) * xi1)).sqrt();
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+}
+
+/// Split `base_name` into its stem and extension, defaulting to `png` if
+/// no extension is given (so callers can pass e.g. `"sky"` or
+/// `"sky.jpg"` as the base name for [`get_skybox_from_faces`]).
+fn split_base_name(base_name: &str) -> (&str, &str) {
+    match base_name.rsplit_once('.') {
+        Some((stem, ext)) => (stem, ext),
+        None => (base_name, "png"),
+    }
+}
+
 /// `image` module measurements of positions in pixels.
 ///
 /// See docs for the explanation of the indices.
@@ -77,22 +642,13 @@ pub struct ImageMeasurements {
 }
 
 impl ImageMeasurements {
-    pub fn new_image(&self, old_image: &DynamicImage) -> Result<Image, ImageError> {
+    pub fn new_image(&self, old_image: &DynamicImage, layout: &NetLayout) -> Result<Image, ImageError> {
         let side = self.measure_side_length();
         let mut new_image = RgbaImage::new(side, side * 6);
 
-        // +X
-        self.copy_face(old_image, &mut new_image, side, 3, 1, 0)?;
-        // -X
-        self.copy_face(old_image, &mut new_image, side, 1, 1, 1)?;
-        // +Y
-        self.copy_face(old_image, &mut new_image, side, 2, 0, 2)?;
-        // -Y
-        self.copy_face(old_image, &mut new_image, side, 2, 2, 3)?;
-        // +Z
-        self.copy_face(old_image, &mut new_image, side, 2, 1, 4)?;
-        // -Z
-        self.copy_face(old_image, &mut new_image, side, 0, 1, 5)?;
+        for (out_idx, face) in layout.faces.iter().enumerate() {
+            self.copy_face(old_image, &mut new_image, side, *face, out_idx)?;
+        }
 
         let image = Image::from_dynamic(
             image::DynamicImage::from(new_image),
@@ -102,32 +658,43 @@ impl ImageMeasurements {
         Ok(image)
     }
 
-    /// Copy a face as part of the new_image creation
+    /// Copy a face as part of the new_image creation, applying the
+    /// face's orientation correction pixel-by-pixel.
     fn copy_face(
         &self,
         old_image: &DynamicImage,
         new_image: &mut ImageBuffer<Rgba<u8>, Vec<u8>>,
         side: u32,
-        x_idx: usize,
-        y_idx: usize,
+        face: FaceLayout,
         out_idx: usize,
     ) -> Result<(), ImageError> {
-        let offset_x = (self.vec_x[x_idx + 1] - self.vec_x[x_idx] - side) / 2;
-        let offset_y = (self.vec_y[y_idx + 1] - self.vec_y[y_idx] - side) / 2;
-        new_image
-            .copy_from(
-                &old_image
-                    .view(
-                        self.vec_x[x_idx] + offset_x,
-                        self.vec_y[y_idx] + offset_y,
-                        side,
-                        side,
-                    )
-                    .to_image(),
-                0,
-                side * (out_idx as u32),
+        let offset_x = (self.vec_x[face.x_idx + 1] - self.vec_x[face.x_idx] - side) / 2;
+        let offset_y = (self.vec_y[face.y_idx + 1] - self.vec_y[face.y_idx] - side) / 2;
+        let source = old_image
+            .view(
+                self.vec_x[face.x_idx] + offset_x,
+                self.vec_y[face.y_idx] + offset_y,
+                side,
+                side,
             )
-            .map_err(|_| ImageError::CopyError)
+            .to_image();
+
+        for v in 0..side {
+            for u in 0..side {
+                let (mut su, mut sv) = (u, v);
+                if face.transpose {
+                    std::mem::swap(&mut su, &mut sv);
+                }
+                if face.flip_x {
+                    su = side - 1 - su;
+                }
+                if face.flip_y {
+                    sv = side - 1 - sv;
+                }
+                new_image.put_pixel(u, side * (out_idx as u32) + v, *source.get_pixel(su, sv));
+            }
+        }
+        Ok(())
     }
 
     /// Find the dimensions of the skybox net in the image.
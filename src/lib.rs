@@ -24,68 +24,195 @@
 
 mod image;
 
+pub use image::NetLayout;
+
 use bevy::{
     prelude::*,
     core_pipeline::Skybox,
     image::CompressedImageFormats,
+    pbr::EnvironmentMapLight,
     render::render_resource::{TextureViewDescriptor, TextureViewDimension},
     render::renderer::RenderDevice,
 };
 
-/// Create a secondary camera with a longer draw distance than the main camera.
-fn create_skybox(
+/// The default `SkyboxPlugin::brightness`, matching what this crate has
+/// always rendered the skybox at.
+const DEFAULT_SKYBOX_BRIGHTNESS: f32 = 1000.0;
+
+/// Overrides `SkyboxPlugin::brightness` for the camera it is attached to,
+/// so a single plugin-wide exposure can still be tuned per camera (e.g. a
+/// dimmer sky in an indoor-looking viewport).
+#[derive(Component, Clone, Copy)]
+pub struct SkyboxBrightness(pub f32);
+
+/// The brightness to render a camera's `Skybox` at, honouring a
+/// [`SkyboxBrightness`] override on that camera if present.
+fn target_brightness(plugin: &SkyboxPlugin, brightness_override: Option<&SkyboxBrightness>) -> f32 {
+    brightness_override.map_or(plugin.brightness, |b| b.0)
+}
+
+/// (Re)build the cubemap and attach it to every `SkyboxCamera` whenever the
+/// requested `SkyboxPlugin::source` changes, so games can switch skies at
+/// runtime (level transitions, weather) via `set_image`/`clear_image` and
+/// not just once at `Startup`.
+fn sync_skybox(
     mut commands: Commands,
     mut plugin: ResMut<SkyboxPlugin>,
     render_device: Res<RenderDevice>,
+    asset_server: Res<AssetServer>,
     mut images: ResMut<Assets<Image>>,
-    camera_query: Query<Entity, With<SkyboxCamera>>,
+    camera_query: Query<(Entity, Option<&SkyboxBrightness>), With<SkyboxCamera>>,
 ) {
-    if let Some(image) = &plugin.image {
-        // Check that the uncompressed format is supported.
-        assert!(CompressedImageFormats::from_features(render_device.features()).contains(CompressedImageFormats::NONE));
+    if plugin.source == plugin.resolved_source {
+        return;
+    }
+    plugin.resolved_source = plugin.source.clone();
+    plugin.fade_elapsed = 0.0;
+
+    if let Some(source) = plugin.source.clone() {
+        // A KTX2 cubemap is already GPU-ready (and typically compressed),
+        // so it skips all of the CPU-side decoding/stacking/irradiance work
+        // below: the `AssetServer` loads and uploads it directly, and the
+        // resulting handle is used as-is. This means `environment_light` is
+        // not available for this source, since there is no raw pixel data
+        // left to derive an irradiance map from.
+        let (skybox_handle, environment_map) = if let SkyboxSource::Ktx2(path) = &source {
+            (asset_server.load(path), None)
+        } else {
+            // Check that the uncompressed format is supported.
+            assert!(CompressedImageFormats::from_features(render_device.features()).contains(CompressedImageFormats::NONE));
+
+            // Get the skybox image for the source given.
+            let mut skybox_image = match &source {
+                SkyboxSource::Image(image) => {
+                    image::get_skybox(image, &plugin.net_layout).expect("Good image")
+                }
+                SkyboxSource::CubeFaces(base_name) => {
+                    image::get_skybox_from_faces(base_name).expect("Good cube faces")
+                }
+                SkyboxSource::CubemapFaces(paths) => {
+                    image::get_skybox_from_face_paths(paths).expect("Good cube faces")
+                }
+                SkyboxSource::Equirectangular(path) => {
+                    image::get_skybox_from_equirectangular(path).expect("Good panorama")
+                }
+                SkyboxSource::Ktx2(_) => unreachable!("handled above"),
+            };
 
-        // Get the skybox image for the image given.
-        let mut skybox_image = image::get_skybox(image).expect("Good image");
+            assert_eq!(skybox_image.texture_descriptor.array_layer_count(), 1);
+            let side = skybox_image.texture_descriptor.size.width;
+            let irradiance_image = plugin
+                .environment_light
+                .map(|_| image::diffuse_irradiance(&skybox_image, side));
+            // Built from the pre-reinterpret, still-2D `skybox_image` (same
+            // as `irradiance_image` above), since `specular_prefilter`
+            // constructs its own multi-mip cube `Image` directly rather
+            // than reusing the single-mip stack-then-reinterpret trick.
+            let specular_image = plugin
+                .environment_light
+                .map(|_| image::specular_prefilter(&skybox_image, side));
+            skybox_image.reinterpret_stacked_2d_as_array(6);
+            assert_eq!(skybox_image.texture_descriptor.array_layer_count(), 6);
 
-        assert_eq!(skybox_image.texture_descriptor.array_layer_count(), 1);
-        skybox_image.reinterpret_stacked_2d_as_array(6);
-        assert_eq!(skybox_image.texture_descriptor.array_layer_count(), 6);
+            skybox_image.texture_view_descriptor = Some(TextureViewDescriptor {
+                dimension: Some(TextureViewDimension::Cube),
+                ..default()
+            });
 
-        skybox_image.texture_view_descriptor = Some(TextureViewDescriptor {
-            dimension: Some(TextureViewDimension::Cube),
-            ..default()
-        });
+            let skybox_handle = images.add(skybox_image);
+
+            let environment_map = plugin.environment_light.map(|intensity| {
+                let mut irradiance_image = irradiance_image.expect("computed above when environment_light is set");
+                irradiance_image.reinterpret_stacked_2d_as_array(6);
+                irradiance_image.texture_view_descriptor = Some(TextureViewDescriptor {
+                    dimension: Some(TextureViewDimension::Cube),
+                    ..default()
+                });
+
+                let mut specular_image = specular_image.expect("computed above when environment_light is set");
+                specular_image.texture_view_descriptor = Some(TextureViewDescriptor {
+                    dimension: Some(TextureViewDimension::Cube),
+                    ..default()
+                });
+
+                EnvironmentMapLight {
+                    diffuse_map: images.add(irradiance_image),
+                    specular_map: images.add(specular_image),
+                    intensity,
+                    ..default()
+                }
+            });
+
+            (skybox_handle, environment_map)
+        };
 
-        let skybox_handle = images.add(skybox_image);
         plugin.handle = Some(skybox_handle.clone());
+        plugin.environment_map = environment_map.clone();
 
-        for cam in camera_query.iter() {
-            commands.entity(cam).insert(Skybox {
+        for (cam, brightness_override) in camera_query.iter() {
+            // When crossfading, start dark and let `fade_skybox_in` ramp the
+            // brightness up over `crossfade_duration`, rather than popping
+            // straight to the new sky.
+            let brightness = if plugin.crossfade_duration > 0.0 {
+                0.0
+            } else {
+                target_brightness(&plugin, brightness_override)
+            };
+
+            let mut entity = commands.entity(cam);
+            entity.insert(Skybox {
                 image: skybox_handle.clone(),
-                brightness: 1000.0,
+                brightness,
                 ..default()
             });
+            if let Some(environment_map) = &environment_map {
+                entity.insert(environment_map.clone());
+            }
         }
     } else {
-        for cam in camera_query.iter() {
+        plugin.handle = None;
+        plugin.environment_map = None;
+        for (cam, _) in camera_query.iter() {
             commands.entity(cam).remove::<Skybox>();
         }
     }
 }
 
+/// Ramp a freshly-swapped skybox's brightness up from zero over
+/// `SkyboxPlugin::crossfade_duration`, so swapping skies isn't an
+/// instantaneous pop.
+fn fade_skybox_in(
+    mut plugin: ResMut<SkyboxPlugin>,
+    time: Res<Time>,
+    mut query: Query<(&mut Skybox, Option<&SkyboxBrightness>)>,
+) {
+    if plugin.crossfade_duration <= 0.0 || plugin.fade_elapsed >= plugin.crossfade_duration {
+        return;
+    }
+    plugin.fade_elapsed = (plugin.fade_elapsed + time.delta_secs()).min(plugin.crossfade_duration);
+    let t = plugin.fade_elapsed / plugin.crossfade_duration;
+    for (mut skybox, brightness_override) in query.iter_mut() {
+        skybox.brightness = target_brightness(&plugin, brightness_override) * t;
+    }
+}
+
 fn new_camera(
     mut commands: Commands,
     plugin: Res<SkyboxPlugin>,
-    camera_query: Query<Entity, (Added<Camera3d>, With<SkyboxCamera>)>,
+    camera_query: Query<(Entity, Option<&SkyboxBrightness>), (Added<Camera3d>, With<SkyboxCamera>)>,
 ) {
     if let Some(skybox_handle) = &plugin.handle {
-        for cam in camera_query.iter() {
+        for (cam, brightness_override) in camera_query.iter() {
             println!("Add camera after");
-            commands.entity(cam).insert(Skybox {
+            let mut entity = commands.entity(cam);
+            entity.insert(Skybox {
                 image: skybox_handle.clone(),
-                brightness: 1000.0,
+                brightness: target_brightness(&plugin, brightness_override),
                 ..default()
             });
+            if let Some(environment_map) = &plugin.environment_map {
+                entity.insert(environment_map.clone());
+            }
         }
     }
 }
@@ -94,33 +221,279 @@ fn new_camera(
 #[derive(Component)]
 pub struct SkyboxCamera;
 
+/// Rotates the rendered sky independently of the camera, e.g. to fake
+/// scrolling clouds, drifting wind, a turning star field, or a day/night
+/// cycle. Attach to the same entity as [`SkyboxCamera`]; this only ever
+/// rotates the sky sampling direction, so the camera's own position and
+/// look direction are unaffected.
+///
+/// `rotation` is the orientation applied to the `Skybox` component each
+/// frame; it is safe to read and write directly (it is re-applied verbatim
+/// every frame, on top of whatever `angular_velocity` integrates), so a
+/// custom system can drive the sky orientation directly instead of (or as
+/// well as) a constant spin.
+#[derive(Component, Clone, Copy)]
+pub struct SkyboxRotation {
+    /// The axis to rotate around.
+    pub axis: Vec3,
+    /// Angular velocity in radians per second.
+    pub angular_velocity: f32,
+    /// The current orientation of the skybox.
+    pub rotation: Quat,
+}
+
+impl SkyboxRotation {
+    /// Spin continuously around `axis` at `angular_velocity` radians per second.
+    pub fn new(axis: Vec3, angular_velocity: f32) -> Self {
+        Self {
+            axis,
+            angular_velocity,
+            rotation: Quat::IDENTITY,
+        }
+    }
+
+    /// Start (or drive) the sky at an explicit orientation, with no
+    /// automatic spin. Useful for a day/night cycle or wind gusts driven by
+    /// a custom system that writes `rotation` directly each frame, rather
+    /// than a constant `angular_velocity`.
+    pub fn from_rotation(rotation: Quat) -> Self {
+        Self {
+            axis: Vec3::Y,
+            angular_velocity: 0.0,
+            rotation,
+        }
+    }
+}
+
+impl Default for SkyboxRotation {
+    /// No rotation, around +Y.
+    fn default() -> Self {
+        Self::new(Vec3::Y, 0.0)
+    }
+}
+
+/// Integrate `SkyboxRotation::angular_velocity` and apply the result to the
+/// camera's `Skybox`.
+fn rotate_skybox(time: Res<Time>, mut query: Query<(&mut SkyboxRotation, &mut Skybox)>) {
+    for (mut spin, mut skybox) in query.iter_mut() {
+        if spin.angular_velocity != 0.0 {
+            let delta = Quat::from_axis_angle(spin.axis.normalize_or_zero(), spin.angular_velocity * time.delta_secs());
+            spin.rotation = (delta * spin.rotation).normalize();
+        }
+        skybox.rotation = spin.rotation;
+    }
+}
+
+/// The image (or set of images) a [`SkyboxPlugin`] resolves its cubemap from.
+#[derive(Clone, PartialEq)]
+enum SkyboxSource {
+    /// A single net/cross-layout image in the assets folder.
+    Image(String),
+    /// Six separate face images in the assets folder, named
+    /// `<base_name>_<suffix>.<ext>` (see [`image::get_skybox_from_faces`]).
+    CubeFaces(String),
+    /// Six explicitly-named face images in the assets folder, in
+    /// +X,-X,+Y,-Y,+Z,-Z order (see [`image::get_skybox_from_face_paths`]).
+    CubemapFaces([String; 6]),
+    /// A pre-built, already GPU-ready compressed cubemap in the assets
+    /// folder, loaded directly through the `AssetServer` rather than
+    /// processed by this crate. Does not support `environment_light`, since
+    /// there is no raw pixel data here to derive an irradiance map from.
+    Ktx2(String),
+    /// A single equirectangular ("360 photo") panorama in the assets
+    /// folder, baked into six cube faces (see
+    /// [`image::get_skybox_from_equirectangular`]).
+    Equirectangular(String),
+}
+
 /// The `SkyboxPlugin` object acts as both the plugin and the resource providing the image name.
 #[derive(Clone, Resource)]
 pub struct SkyboxPlugin {
-    /// The filename of the image in the assets folder.
-    image: Option<String>,
+    /// The image(s) to load the skybox from, in the assets folder.
+    source: Option<SkyboxSource>,
+    /// The arrangement of faces expected within a single net image (ignored
+    /// except for [`SkyboxSource::Image`] — the other sources don't have a
+    /// net to parse).
+    net_layout: NetLayout,
+    /// If set, also attach an `EnvironmentMapLight` derived from the skybox
+    /// image to `SkyboxCamera`, at this intensity.
+    environment_light: Option<f32>,
+    /// The exposure to render the skybox at, applied as the `Skybox`
+    /// component's `brightness`. Overridable per camera with
+    /// [`SkyboxBrightness`].
+    brightness: f32,
+    /// How long, in seconds, to fade a newly swapped-in skybox's brightness
+    /// up from zero. Zero (the default) swaps instantly.
+    crossfade_duration: f32,
+    /// The `source` a cubemap has actually been built for; compared against
+    /// `source` each frame to detect a runtime `set_image`/`clear_image`
+    /// call that needs a rebuild.
+    resolved_source: Option<SkyboxSource>,
+    /// How long the current fade-in has been running for.
+    fade_elapsed: f32,
     handle: Option<Handle<Image>>,
+    /// The `EnvironmentMapLight` resolved alongside `handle`, if
+    /// `environment_light` is set; cached here (rather than only ever
+    /// built locally in `sync_skybox`) so `new_camera` can attach it to
+    /// cameras spawned after the initial resolve, not just the ones that
+    /// existed when the cubemap was (re)built.
+    environment_map: Option<EnvironmentMapLight>,
 }
 
 impl SkyboxPlugin {
-    pub fn from_image_file(image: &str) -> SkyboxPlugin {
+    /// Build a fresh `SkyboxPlugin` for `source` (or `None` for
+    /// [`empty`](Self::empty)), with every other field at its default.
+    /// Shared by the `from_*`/`empty` constructors so adding a field only
+    /// means updating it here.
+    fn with_source(source: Option<SkyboxSource>) -> SkyboxPlugin {
         Self {
-            image: Some(image.to_owned()),
+            source,
+            net_layout: NetLayout::default(),
+            environment_light: None,
+            brightness: DEFAULT_SKYBOX_BRIGHTNESS,
+            crossfade_duration: 0.0,
+            resolved_source: None,
+            fade_elapsed: 0.0,
             handle: None,
+            environment_map: None,
         }
     }
 
+    pub fn from_image_file(image: &str) -> SkyboxPlugin {
+        Self::with_source(Some(SkyboxSource::Image(image.to_owned())))
+    }
+
+    /// Load the skybox from six separate face images named
+    /// `<base_name>_px.png`, `<base_name>_nx.png`, ... (or the
+    /// `_right`/`_left`/`_up`/`_down`/`_front`/`_back` aliases), rather than
+    /// a single net/cross-layout image. See [`image::get_skybox_from_faces`]
+    /// for the exact naming scheme.
+    pub fn from_cube_faces(base_name: &str) -> SkyboxPlugin {
+        Self::with_source(Some(SkyboxSource::CubeFaces(base_name.to_owned())))
+    }
+
+    /// Load the skybox from six explicitly-named face images, in
+    /// +X,-X,+Y,-Y,+Z,-Z order, rather than discovering them by suffix
+    /// convention like [`from_cube_faces`](Self::from_cube_faces). Useful
+    /// for art that doesn't follow the `_px`/`_nx`/... naming scheme. The
+    /// faces are decoded and stacked into a single-mip cubemap. See
+    /// [`image::get_skybox_from_face_paths`] for path resolution.
+    pub fn from_cubemap_faces(faces: [&str; 6]) -> SkyboxPlugin {
+        Self::with_source(Some(SkyboxSource::CubemapFaces(faces.map(str::to_owned))))
+    }
+
+    /// Load a pre-built, GPU-ready compressed cubemap (the KTX2 cubemap
+    /// format Bevy itself uses) from the assets folder, bypassing this
+    /// crate's layout-detection and CPU-side processing entirely. Gives
+    /// higher-resolution, mip-mapped skies without re-packing them into a
+    /// cross layout. Does not support
+    /// [`with_environment_light`](Self::with_environment_light), since
+    /// there is no raw pixel data here to derive an irradiance map from.
+    pub fn from_ktx2(path: &str) -> SkyboxPlugin {
+        Self::with_source(Some(SkyboxSource::Ktx2(path.to_owned())))
+    }
+
+    /// Load the skybox from a single equirectangular ("360 photo") panorama
+    /// rather than a cross/strip net or separate faces. Broadens the set of
+    /// usable sky assets to the common lat/long format many panorama tools
+    /// and cameras export, without asking users to convert images
+    /// externally. See [`image::get_skybox_from_equirectangular`] for the
+    /// baking details.
+    pub fn from_equirectangular(path: &str) -> SkyboxPlugin {
+        Self::with_source(Some(SkyboxSource::Equirectangular(path.to_owned())))
+    }
+
     /// Does not create an image cube, props must then be added to SkyboxCamera
     /// with a `Skybox` component.
     pub fn empty() -> SkyboxPlugin {
-        Self { image: None, handle: None }
+        Self::with_source(None)
+    }
+
+    /// Use a non-default arrangement of faces when parsing a net image
+    /// loaded with [`from_image_file`](Self::from_image_file), e.g.
+    /// `NetLayout::horizontal_cross()`.
+    pub fn with_net_layout(mut self, net_layout: NetLayout) -> SkyboxPlugin {
+        self.net_layout = net_layout;
+        self
+    }
+
+    /// Also attach a `bevy::pbr::EnvironmentMapLight` derived from the
+    /// skybox image to `SkyboxCamera`, at the given intensity, so PBR
+    /// meshes in the scene pick up ambient colour and reflections from
+    /// the sky instead of getting no lighting contribution from it.
+    pub fn with_environment_light(mut self, intensity: f32) -> SkyboxPlugin {
+        self.environment_light = Some(intensity);
+        self
+    }
+
+    /// Set the exposure the skybox is rendered at, so it can be dialled to
+    /// sit correctly against `DirectionalLight`-lit geometry rather than
+    /// rendering at whatever the source image happens to encode. Can be
+    /// overridden per camera with [`SkyboxBrightness`].
+    pub fn with_brightness(mut self, brightness: f32) -> SkyboxPlugin {
+        self.brightness = brightness;
+        self
+    }
+
+    /// Fade a newly swapped-in skybox's brightness up from zero over
+    /// `duration` seconds, rather than popping to it instantly.
+    pub fn with_crossfade(mut self, duration: f32) -> SkyboxPlugin {
+        self.crossfade_duration = duration;
+        self
+    }
+
+    /// Switch to a single net/cross-layout image at runtime. Takes effect
+    /// the next time the `SkyboxPlugin` systems run.
+    pub fn set_image(&mut self, image: &str) {
+        self.source = Some(SkyboxSource::Image(image.to_owned()));
+    }
+
+    /// Switch to six separate face images at runtime, as per
+    /// [`from_cube_faces`](Self::from_cube_faces). Takes effect the next
+    /// time the `SkyboxPlugin` systems run.
+    pub fn set_cube_faces(&mut self, base_name: &str) {
+        self.source = Some(SkyboxSource::CubeFaces(base_name.to_owned()));
+    }
+
+    /// Switch to six explicitly-named face images at runtime, as per
+    /// [`from_cubemap_faces`](Self::from_cubemap_faces). Takes effect the
+    /// next time the `SkyboxPlugin` systems run.
+    pub fn set_cubemap_faces(&mut self, faces: [&str; 6]) {
+        self.source = Some(SkyboxSource::CubemapFaces(faces.map(str::to_owned)));
+    }
+
+    /// Switch to a pre-built KTX2 cubemap at runtime, as per
+    /// [`from_ktx2`](Self::from_ktx2). Takes effect the next time the
+    /// `SkyboxPlugin` systems run.
+    pub fn set_ktx2(&mut self, path: &str) {
+        self.source = Some(SkyboxSource::Ktx2(path.to_owned()));
+    }
+
+    /// Switch to a single equirectangular panorama at runtime, as per
+    /// [`from_equirectangular`](Self::from_equirectangular). Takes effect
+    /// the next time the `SkyboxPlugin` systems run.
+    pub fn set_equirectangular(&mut self, path: &str) {
+        self.source = Some(SkyboxSource::Equirectangular(path.to_owned()));
+    }
+
+    /// Remove the skybox, as per [`empty`](Self::empty). Takes effect the
+    /// next time the `SkyboxPlugin` systems run.
+    pub fn clear_image(&mut self) {
+        self.source = None;
     }
 }
 
 impl Plugin for SkyboxPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(self.clone())
-            .add_systems(Startup, create_skybox)
-            .add_systems(Update, new_camera);
+            .add_systems(
+                Update,
+                (
+                    sync_skybox.before(new_camera),
+                    new_camera,
+                    rotate_skybox,
+                    fade_skybox_in,
+                ),
+            );
     }
 }